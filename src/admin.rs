@@ -0,0 +1,54 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{extract::Extension, http::StatusCode, routing::get, AddExtensionLayer, Router};
+use metrics_exporter_prometheus::PrometheusHandle;
+
+use crate::sink::LogSink;
+
+/// Serves the operator-facing admin surface (metrics + health checks) on
+/// its own address, separate from the public ingest port, so metrics don't
+/// have to be exposed alongside the thing being measured.
+pub async fn serve(
+    addr: SocketAddr,
+    sink: Arc<dyn LogSink>,
+    prometheus_handle: PrometheusHandle,
+) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .layer(AddExtensionLayer::new(sink))
+        .layer(AddExtensionLayer::new(prometheus_handle));
+
+    tracing::debug!("admin surface listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+async fn metrics(
+    Extension(sink): Extension<Arc<dyn LogSink>>,
+    Extension(prometheus_handle): Extension<PrometheusHandle>,
+) -> String {
+    if let Some((in_use, idle)) = sink.pool_state() {
+        metrics::gauge!("pool_connections_in_use").set(in_use as f64);
+        metrics::gauge!("pool_connections_idle").set(idle as f64);
+    }
+    prometheus_handle.render()
+}
+
+/// Liveness: the process is up and serving requests.
+async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Readiness: the sink can actually accept writes right now (e.g. a
+/// connection can be borrowed from the HBase pool), so orchestrators can
+/// gate traffic on more than just "the process started".
+async fn readyz(Extension(sink): Extension<Arc<dyn LogSink>>) -> (StatusCode, String) {
+    match sink.ready().await {
+        Ok(()) => (StatusCode::OK, "ready".to_string()),
+        Err(err) => (StatusCode::SERVICE_UNAVAILABLE, err.to_string()),
+    }
+}
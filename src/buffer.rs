@@ -0,0 +1,123 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    resilience::Resilience,
+    sink::{Destination, LogSink},
+    Logs,
+};
+
+/// Handle handed to request handlers to enqueue logs for background
+/// flushing. Cloning and sending is cheap; the channel is bounded, so a
+/// full buffer applies backpressure to producers instead of dropping data.
+pub type Flusher = mpsc::Sender<(Destination, Logs)>;
+
+/// Tuning knobs for the background flusher task.
+pub struct FlusherConfig {
+    pub channel_capacity: usize,
+    pub max_batch: usize,
+    pub flush_interval: Duration,
+}
+
+/// Spawns a background task that accumulates logs pushed through the
+/// returned [`Flusher`] and drains them to `sink`, grouped by destination,
+/// once either `max_batch` logs have buffered or the next `flush_interval`
+/// tick arrives, whichever comes first. Ticks follow a fixed schedule and
+/// are not reset by a size-triggered flush.
+///
+/// Dropping every clone of the returned `Flusher` (e.g. once the HTTP
+/// server shuts down) closes the channel, causing the task to flush
+/// whatever remains and exit — the returned [`JoinHandle`](tokio::task::JoinHandle)
+/// can be awaited to wait for that final flush.
+pub fn spawn(
+    sink: Arc<dyn LogSink>,
+    resilience: Resilience,
+    config: FlusherConfig,
+) -> (Flusher, tokio::task::JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel(config.channel_capacity);
+    let handle = tokio::spawn(run(
+        rx,
+        sink,
+        Arc::new(resilience),
+        config.max_batch,
+        config.flush_interval,
+    ));
+    (tx, handle)
+}
+
+async fn run(
+    mut rx: mpsc::Receiver<(Destination, Logs)>,
+    sink: Arc<dyn LogSink>,
+    resilience: Arc<Resilience>,
+    max_batch: usize,
+    flush_interval: Duration,
+) {
+    let mut buffer: BTreeMap<Destination, Logs> = BTreeMap::new();
+    let mut buffered = 0;
+    let mut ticker = tokio::time::interval(flush_interval);
+    // Per-destination retry tasks spawned by `flush`, tracked so a graceful
+    // shutdown (channel closed) can wait for them instead of abandoning
+    // in-flight writes.
+    let mut in_flight: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+
+    loop {
+        tokio::select! {
+            item = rx.recv() => {
+                match item {
+                    Some((destination, logs)) => {
+                        buffered += logs.len();
+                        buffer.entry(destination).or_default().extend(logs);
+                        if buffered >= max_batch {
+                            flush(&sink, &resilience, &mut buffer, &mut buffered, &mut in_flight);
+                        }
+                    }
+                    None => {
+                        tracing::debug!("flusher channel closed, flushing remaining logs");
+                        flush(&sink, &resilience, &mut buffer, &mut buffered, &mut in_flight);
+                        await_in_flight(in_flight).await;
+                        return;
+                    }
+                }
+            }
+            _ = ticker.tick() => {
+                flush(&sink, &resilience, &mut buffer, &mut buffered, &mut in_flight);
+            }
+        }
+        in_flight.retain(|handle| !handle.is_finished());
+    }
+}
+
+/// Drains `buffer`, spawning one task per destination group so that a
+/// retry storm against one table's writes can't block flushes to other,
+/// healthy tables, or stall draining of the channel that backpressures
+/// producers.
+fn flush(
+    sink: &Arc<dyn LogSink>,
+    resilience: &Arc<Resilience>,
+    buffer: &mut BTreeMap<Destination, Logs>,
+    buffered: &mut usize,
+    in_flight: &mut Vec<tokio::task::JoinHandle<()>>,
+) {
+    if buffer.is_empty() {
+        return;
+    }
+    for (destination, logs) in std::mem::take(buffer) {
+        let sink = Arc::clone(sink);
+        let resilience = Arc::clone(resilience);
+        in_flight.push(tokio::spawn(async move {
+            resilience
+                .write_with_retry(sink.as_ref(), &destination, logs)
+                .await;
+        }));
+    }
+    *buffered = 0;
+}
+
+async fn await_in_flight(in_flight: Vec<tokio::task::JoinHandle<()>>) {
+    for handle in in_flight {
+        if let Err(err) = handle.await {
+            tracing::error!(%err, "a per-destination flush task panicked");
+        }
+    }
+}
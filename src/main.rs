@@ -1,57 +1,173 @@
-use axum::{extract::Extension, http::StatusCode, routing::post, AddExtensionLayer, Json, Router};
-use bb8::Pool;
-use clap::Parser;
-use hbase_thrift::{
-    hbase::HbaseSyncClient, BatchMutationBuilder, MutationBuilder, THbaseSyncClientExt,
+mod admin;
+mod buffer;
+mod resilience;
+mod sink;
+
+use axum::{
+    extract::{Extension, Path},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    AddExtensionLayer, Json, Router,
 };
+use clap::Parser;
+use metrics_exporter_prometheus::PrometheusBuilder;
 use serde_json::value::RawValue;
-use std::{collections::BTreeMap, net::SocketAddr, time::Duration};
-use thrift::{
-    protocol::{TBinaryInputProtocol, TBinaryOutputProtocol},
-    transport::{
-        ReadHalf, TBufferedReadTransport, TBufferedWriteTransport, TTcpChannel, WriteHalf,
-    },
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    num::{NonZeroU32, NonZeroU64},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
 };
-use thrift_pool::{MakeThriftConnectionFromAddrs, ThriftConnectionManager};
+use tokio::signal::unix::{signal, SignalKind};
 use tower_http::trace::TraceLayer;
 
-type Client = HbaseSyncClient<
-    TBinaryInputProtocol<TBufferedReadTransport<ReadHalf<TTcpChannel>>>,
-    TBinaryOutputProtocol<TBufferedWriteTransport<WriteHalf<TTcpChannel>>>,
->;
-type ConnectionManager<S> = ThriftConnectionManager<MakeThriftConnectionFromAddrs<Client, S>>;
-type ConnectionPool<S> = Pool<ConnectionManager<S>>;
+use buffer::{Flusher, FlusherConfig};
+use resilience::{DeadLetter, Resilience};
+use sink::{Destination, HbaseOptions, LogSink};
 
-type Logs = Vec<BTreeMap<String, Box<RawValue>>>;
+pub type Logs = Vec<BTreeMap<String, Box<RawValue>>>;
 
-#[derive(Debug, Clone)]
-struct Config {
-    pub column_family: String,
-    pub table_name: String,
-}
+const TABLE_HEADER: &str = "x-hbase-table";
+const COLUMN_FAMILY_HEADER: &str = "x-hbase-column-family";
 
 #[derive(Parser)]
 #[clap(version, about, author)]
 struct Cli {
-    /// Address where hbase's thrift endpoint is exposed
-    #[clap(long, default_value = "localhost:9090", env)]
-    pub hbase_addr: String,
+    /// Where logs are written, e.g. `hbase://host:9090`, `memory://`, or
+    /// `stdout://`
+    #[clap(long, default_value = "hbase://localhost:9090", env)]
+    pub sink: String,
 
-    /// Name of the table in hbase where logs will be written
+    /// Default table that logs are written to when no routing override applies
     #[clap(long, default_value = "logs", env)]
     pub table_name: String,
 
-    /// Name of the column family where logs will be written
+    /// Default column family that logs are written to when no routing override applies
     #[clap(long, default_value = "data", env)]
     pub column_family: String,
 
-    /// The path where the endpoint will be enabled
+    /// Name of a field in each log object that, when present, overrides the
+    /// destination table for that log
+    #[clap(long, env)]
+    pub table_field: Option<String>,
+
+    /// Name of a field in each log object that, when present, overrides the
+    /// destination column family for that log
+    #[clap(long, env)]
+    pub column_family_field: Option<String>,
+
+    /// How to derive each row's key: `timestamp`, `field:<name>`, or
+    /// `composite:<strategy>,<strategy>,...` (only used by the `hbase` sink)
+    #[clap(long, default_value = "timestamp", env)]
+    pub row_key_strategy: sink::RowKeyStrategy,
+
+    /// Number of buckets to spread row keys across to avoid hotspotting
+    /// (only used by the `hbase` sink). When unset, no salting is applied.
+    /// Must be nonzero.
+    #[clap(long, env)]
+    pub salt_buckets: Option<NonZeroU32>,
+
+    /// Width (in digits) that the zero-padded salt bucket prefix is formatted to
+    #[clap(long, default_value = "2", env)]
+    pub salt_width: usize,
+
+    /// Maximum number of buffered logs before the flusher drains them to the sink
+    #[clap(long, default_value = "500", env)]
+    pub max_batch: usize,
+
+    /// Maximum time, in milliseconds, that logs sit in the buffer before being flushed.
+    /// Must be nonzero.
+    #[clap(long, default_value = "1000", env)]
+    pub flush_interval_ms: NonZeroU64,
+
+    /// Capacity of the channel between request handlers and the flusher task.
+    /// Once full, handlers await free space instead of dropping logs.
+    #[clap(long, default_value = "1024", env)]
+    pub channel_capacity: usize,
+
+    /// Maximum number of times a failed put is retried, with exponential
+    /// backoff, before the batch is routed to the dead letter path
+    #[clap(long, default_value = "5", env)]
+    pub max_retries: u32,
+
+    /// Base delay for the exponential backoff between retries, in milliseconds
+    #[clap(long, default_value = "100", env)]
+    pub retry_base_delay_ms: u64,
+
+    /// Append-only file that batches are written to once they exhaust their
+    /// retries. When unset, exhausted batches are only logged and dropped.
+    #[clap(long, env)]
+    pub dead_letter_path: Option<PathBuf>,
+
+    /// The path where the endpoint will be enabled. A log's destination
+    /// table can also be given as an extra path segment under this route.
     #[clap(long, default_value = "/", env)]
     pub listen_route: String,
 
     /// Socket address on which to start the server (address:port)
     #[clap(long, default_value = "0.0.0.0:3000", env)]
     pub listen_addr: SocketAddr,
+
+    /// Socket address for the admin surface (`/metrics`, `/healthz`, `/readyz`),
+    /// kept separate from `listen_addr` so it need not be exposed publicly
+    #[clap(long, default_value = "0.0.0.0:9000", env)]
+    pub admin_addr: SocketAddr,
+}
+
+/// How a log's destination table/column family are resolved when none of
+/// the per-log/per-request overrides apply.
+#[derive(Debug, Clone)]
+struct RoutingConfig {
+    pub default_table: String,
+    pub default_column_family: String,
+    pub table_field: Option<String>,
+    pub column_family_field: Option<String>,
+}
+
+impl RoutingConfig {
+    /// Resolve the destination for `log`, consulting (in order of priority)
+    /// the configured log field, the request headers, the URL path segment,
+    /// and finally the configured defaults.
+    fn resolve(
+        &self,
+        headers: &HeaderMap,
+        path_table: Option<&str>,
+        log: &BTreeMap<String, Box<RawValue>>,
+    ) -> Destination {
+        let table = self
+            .table_field
+            .as_deref()
+            .and_then(|field| log.get(field))
+            .map(|v| v.get().trim_matches('"').to_string())
+            .or_else(|| {
+                headers
+                    .get(TABLE_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+            })
+            .or_else(|| path_table.map(str::to_string))
+            .unwrap_or_else(|| self.default_table.clone());
+
+        let column_family = self
+            .column_family_field
+            .as_deref()
+            .and_then(|field| log.get(field))
+            .map(|v| v.get().trim_matches('"').to_string())
+            .or_else(|| {
+                headers
+                    .get(COLUMN_FAMILY_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| self.default_column_family.clone());
+
+        Destination {
+            table,
+            column_family,
+        }
+    }
 }
 
 #[tokio::main]
@@ -60,54 +176,251 @@ async fn main() -> anyhow::Result<()> {
 
     let cli = Cli::parse();
 
-    let manager =
-        MakeThriftConnectionFromAddrs::<Client, _>::new(cli.hbase_addr).into_connection_manager();
-    let pool = Pool::builder()
-        .connection_timeout(Duration::from_secs(5))
-        .build(manager)
-        .await?;
+    let salting = cli.salt_buckets.map(|num_buckets| sink::Salting {
+        num_buckets,
+        width: cli.salt_width,
+    });
+    let hbase_opts = HbaseOptions {
+        row_key_strategy: cli.row_key_strategy,
+        salting,
+    };
+    let sink: Arc<dyn LogSink> = Arc::from(sink::from_addr(&cli.sink, hbase_opts).await?);
+
+    let prometheus_handle = PrometheusBuilder::new().install_recorder()?;
+    let admin_sink = Arc::clone(&sink);
+    let admin_addr = cli.admin_addr;
+    tokio::spawn(async move {
+        if let Err(err) = admin::serve(admin_addr, admin_sink, prometheus_handle).await {
+            tracing::error!(%err, "admin server exited with an error");
+        }
+    });
+
+    let routing = RoutingConfig {
+        default_table: cli.table_name,
+        default_column_family: cli.column_family,
+        table_field: cli.table_field,
+        column_family_field: cli.column_family_field,
+    };
+
+    let resilience = Resilience {
+        max_retries: cli.max_retries,
+        base_delay: Duration::from_millis(cli.retry_base_delay_ms),
+        dead_letter: DeadLetter::new(cli.dead_letter_path),
+    };
 
+    let (flusher, flusher_handle) = buffer::spawn(
+        sink,
+        resilience,
+        FlusherConfig {
+            channel_capacity: cli.channel_capacity,
+            max_batch: cli.max_batch,
+            flush_interval: Duration::from_millis(cli.flush_interval_ms.get()),
+        },
+    );
+
+    let route_with_table = format!("{}/:table", cli.listen_route.trim_end_matches('/'));
     let app = Router::new()
-        .route("/", post(put_logs))
-        .layer(AddExtensionLayer::new(pool))
-        .layer(AddExtensionLayer::new(Config {
-            column_family: cli.column_family,
-            table_name: cli.table_name,
-        }))
+        .route(&cli.listen_route, post(put_logs))
+        .route(&route_with_table, post(put_logs_with_table))
+        .layer(AddExtensionLayer::new(flusher))
+        .layer(AddExtensionLayer::new(routing))
         .layer(TraceLayer::new_for_http());
 
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let shutdown = async move {
+        tokio::select! {
+            _ = sigterm.recv() => {},
+            _ = tokio::signal::ctrl_c() => {},
+        }
+        tracing::info!("shutdown signal received, draining in-flight requests");
+    };
+
     tracing::debug!("listening on {}", cli.listen_addr);
     axum::Server::bind(&cli.listen_addr)
         .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown)
         .await?;
+
+    // `app` (and every clone of `flusher` it held) is dropped by the time
+    // `serve` returns, so the channel is closed and the flusher will flush
+    // its remaining buffer and exit on its own; wait for that to finish.
+    flusher_handle.await?;
     Ok(())
 }
 
-async fn put_logs<'a>(
+async fn put_logs(
+    headers: HeaderMap,
+    Extension(flusher): Extension<Flusher>,
+    Extension(routing): Extension<RoutingConfig>,
     Json(logs): Json<Logs>,
-    Extension(pool): Extension<ConnectionPool<String>>,
-    Extension(config): Extension<Config>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    let mut conn = pool.get().await.map_err(internal_error)?;
-    let mut row_batches = Vec::new();
+    ingest(logs, &headers, None, &flusher, &routing).await
+}
+
+async fn put_logs_with_table(
+    Path(table): Path<String>,
+    headers: HeaderMap,
+    Extension(flusher): Extension<Flusher>,
+    Extension(routing): Extension<RoutingConfig>,
+    Json(logs): Json<Logs>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    ingest(logs, &headers, Some(table.as_str()), &flusher, &routing).await
+}
+
+/// Groups `logs` by their resolved destination and enqueues one item per
+/// group onto the flusher channel, so a single request can fan out to
+/// several tables/column families. Returns as soon as everything is
+/// enqueued; the actual write to the sink happens in the background.
+async fn ingest(
+    logs: Logs,
+    headers: &HeaderMap,
+    path_table: Option<&str>,
+    flusher: &Flusher,
+    routing: &RoutingConfig,
+) -> Result<StatusCode, (StatusCode, String)> {
+    metrics::counter!("logs_ingested_total").increment(logs.len() as u64);
+
+    let mut grouped: BTreeMap<Destination, Logs> = BTreeMap::new();
     for log in logs {
-        let mut bmb = <BatchMutationBuilder>::default();
-        for (k, v) in log {
-            let mut mb = MutationBuilder::default();
-            mb.value(v.get());
-            mb.column(config.column_family.clone(), k);
-            bmb.mutation(mb);
-        }
-        row_batches.push(bmb.build());
+        // Routing fields are only consulted to pick a destination, not
+        // stripped from the record: the caller may also want them stored
+        // as ordinary log data, and a header/path override can win instead
+        // of the field, so deleting it unconditionally would be wrong.
+        let destination = routing.resolve(headers, path_table, &log);
+        grouped.entry(destination).or_default().push(log);
     }
-    conn.put(&config.table_name, row_batches, None, None)
-        .map_err(internal_error)?;
-    Ok(StatusCode::CREATED)
+
+    for group in grouped {
+        flusher.send(group).await.map_err(|err| {
+            internal_error(anyhow::anyhow!("failed to enqueue logs for flushing: {err}"))
+        })?;
+    }
+    Ok(StatusCode::ACCEPTED)
 }
 
-fn internal_error<E>(err: E) -> (StatusCode, String)
-where
-    E: std::error::Error,
-{
+fn internal_error(err: anyhow::Error) -> (StatusCode, String) {
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::value::RawValue;
+
+    use super::*;
+    use sink::MemorySink;
+
+    fn log(pairs: &[(&str, &str)]) -> BTreeMap<String, Box<RawValue>> {
+        pairs
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    RawValue::from_string(format!("{v:?}")).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn routing() -> RoutingConfig {
+        RoutingConfig {
+            default_table: "default-table".to_string(),
+            default_column_family: "default-cf".to_string(),
+            table_field: Some("table_override".to_string()),
+            column_family_field: Some("cf_override".to_string()),
+        }
+    }
+
+    #[test]
+    fn resolve_prefers_log_field_over_header_over_path_over_default() {
+        let routing = routing();
+        let mut headers = HeaderMap::new();
+        headers.insert(TABLE_HEADER, "header-table".parse().unwrap());
+
+        let destination = routing.resolve(
+            &headers,
+            Some("path-table"),
+            &log(&[("table_override", "field-table")]),
+        );
+        assert_eq!(destination.table, "field-table");
+    }
+
+    #[test]
+    fn resolve_falls_back_to_header_when_field_is_absent() {
+        let routing = routing();
+        let mut headers = HeaderMap::new();
+        headers.insert(TABLE_HEADER, "header-table".parse().unwrap());
+
+        let destination = routing.resolve(&headers, Some("path-table"), &log(&[]));
+        assert_eq!(destination.table, "header-table");
+    }
+
+    #[test]
+    fn resolve_falls_back_to_path_when_field_and_header_are_absent() {
+        let routing = routing();
+        let destination = routing.resolve(&HeaderMap::new(), Some("path-table"), &log(&[]));
+        assert_eq!(destination.table, "path-table");
+    }
+
+    #[test]
+    fn resolve_falls_back_to_default_when_nothing_else_applies() {
+        let routing = routing();
+        let destination = routing.resolve(&HeaderMap::new(), None, &log(&[]));
+        assert_eq!(destination.table, "default-table");
+        assert_eq!(destination.column_family, "default-cf");
+    }
+
+    #[test]
+    fn resolve_does_not_strip_the_routing_fields_from_the_log() {
+        let routing = routing();
+        let entry = log(&[("table_override", "field-table")]);
+        routing.resolve(&HeaderMap::new(), None, &entry);
+        assert!(entry.contains_key("table_override"));
+    }
+
+    #[tokio::test]
+    async fn ingest_groups_by_destination_and_flushes_to_the_sink() {
+        let sink = Arc::new(MemorySink::default());
+        let (flusher, handle) = buffer::spawn(
+            sink.clone(),
+            Resilience {
+                max_retries: 0,
+                base_delay: Duration::from_millis(1),
+                dead_letter: DeadLetter::new(None),
+            },
+            FlusherConfig {
+                channel_capacity: 16,
+                max_batch: usize::MAX,
+                flush_interval: Duration::from_secs(3600),
+            },
+        );
+        let routing = routing();
+
+        let logs = vec![
+            log(&[("table_override", "a"), ("value", "1")]),
+            log(&[("table_override", "b"), ("value", "2")]),
+        ];
+        let status = ingest(logs, &HeaderMap::new(), None, &flusher, &routing)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        // Drop the only `Flusher` handle so the channel closes, which makes
+        // the flusher drain its buffer and exit instead of waiting for the
+        // (1-hour) flush interval or a full batch.
+        drop(flusher);
+        handle.await.unwrap();
+
+        let written = sink.snapshot().await;
+        assert_eq!(written.len(), 2);
+        assert!(written.contains_key(&Destination {
+            table: "a".to_string(),
+            column_family: "default-cf".to_string(),
+        }));
+        assert!(written.contains_key(&Destination {
+            table: "b".to_string(),
+            column_family: "default-cf".to_string(),
+        }));
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    sink::{Destination, LogSink},
+    Logs,
+};
+
+/// Routes batches that exhausted their retries somewhere durable instead of
+/// discarding them: an append-only, newline-delimited JSON file when
+/// `--dead-letter-path` is set, otherwise nothing (the failure is only logged).
+pub struct DeadLetter {
+    path: Option<PathBuf>,
+}
+
+impl DeadLetter {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    async fn write(&self, destination: &Destination, logs: &Logs) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        for log in logs {
+            let record = serde_json::json!({
+                "table": destination.table,
+                "column_family": destination.column_family,
+                "log": log,
+            });
+            file.write_all(record.to_string().as_bytes()).await?;
+            file.write_all(b"\n").await?;
+        }
+        Ok(())
+    }
+}
+
+/// Retries a transient `put` failure with exponential backoff (full jitter:
+/// a random delay between zero and the exponential cap) up to `max_retries`
+/// times, then hands the batch off to `dead_letter` instead of dropping it.
+pub struct Resilience {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub dead_letter: DeadLetter,
+}
+
+impl Resilience {
+    pub async fn write_with_retry(&self, sink: &dyn LogSink, destination: &Destination, logs: Logs) {
+        let mut attempt = 0;
+        loop {
+            let started_at = Instant::now();
+            let result = sink.write_logs(destination, logs.clone()).await;
+            metrics::histogram!("put_duration_seconds").record(started_at.elapsed().as_secs_f64());
+
+            match result {
+                Ok(()) => {
+                    metrics::counter!("batches_written_total").increment(1);
+                    return;
+                }
+                Err(err) if attempt < self.max_retries => {
+                    metrics::counter!("put_retries_total").increment(1);
+                    let delay = jittered_delay(self.base_delay, attempt);
+                    tracing::warn!(
+                        %err,
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        table = %destination.table,
+                        "put failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        %err,
+                        attempt,
+                        table = %destination.table,
+                        "put failed after exhausting retries, routing to dead letter"
+                    );
+                    if let Err(dead_letter_err) = self.dead_letter.write(destination, &logs).await
+                    {
+                        tracing::error!(
+                            %dead_letter_err,
+                            table = %destination.table,
+                            "failed to write dead letter record, logs dropped"
+                        );
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn jittered_delay(base_delay: Duration, attempt: u32) -> Duration {
+    let cap = Duration::from_secs(30);
+    let exponential = base_delay
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(cap);
+    let jitter_ms = random_u64() % (exponential.as_millis() as u64 + 1);
+    Duration::from_millis(jitter_ms)
+}
+
+/// A small source of randomness for jitter. Not cryptographically secure,
+/// just enough to decorrelate retries from concurrent callers.
+fn random_u64() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::{Hash, Hasher},
+    num::NonZeroU32,
+    str::FromStr,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bb8::Pool;
+use hbase_thrift::{
+    hbase::HbaseSyncClient, BatchMutationBuilder, MutationBuilder, THbaseSyncClientExt,
+};
+use serde_json::value::RawValue;
+use thrift::{
+    protocol::{TBinaryInputProtocol, TBinaryOutputProtocol},
+    transport::{
+        ReadHalf, TBufferedReadTransport, TBufferedWriteTransport, TTcpChannel, WriteHalf,
+    },
+};
+use thrift_pool::{MakeThriftConnectionFromAddrs, ThriftConnectionManager};
+
+use super::{Destination, LogSink};
+use crate::Logs;
+
+type Client = HbaseSyncClient<
+    TBinaryInputProtocol<TBufferedReadTransport<ReadHalf<TTcpChannel>>>,
+    TBinaryOutputProtocol<TBufferedWriteTransport<WriteHalf<TTcpChannel>>>,
+>;
+type ConnectionManager<S> = ThriftConnectionManager<MakeThriftConnectionFromAddrs<Client, S>>;
+type ConnectionPool<S> = Pool<ConnectionManager<S>>;
+
+/// How the row key for a log entry is derived before it is written to HBase.
+#[derive(Debug, Clone)]
+pub enum RowKeyStrategy {
+    /// Use the current time (nanoseconds since the epoch) as the row key.
+    Timestamp,
+    /// Take the row key verbatim from a field of the incoming log map.
+    Field(String),
+    /// Join several strategies together with `#` to form a composite key.
+    Composite(Vec<RowKeyStrategy>),
+}
+
+impl FromStr for RowKeyStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(field) = s.strip_prefix("field:") {
+            return Ok(RowKeyStrategy::Field(field.to_string()));
+        }
+        if let Some(rest) = s.strip_prefix("composite:") {
+            let parts = rest
+                .split(',')
+                .map(RowKeyStrategy::from_str)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            return Ok(RowKeyStrategy::Composite(parts));
+        }
+        match s {
+            "timestamp" => Ok(RowKeyStrategy::Timestamp),
+            other => Err(anyhow::anyhow!("unknown row key strategy: {other}")),
+        }
+    }
+}
+
+impl RowKeyStrategy {
+    /// Derive the unsalted row key for `log` according to this strategy.
+    fn row_key(&self, log: &BTreeMap<String, Box<RawValue>>) -> String {
+        match self {
+            RowKeyStrategy::Timestamp => chrono::Utc::now().timestamp_nanos().to_string(),
+            RowKeyStrategy::Field(field) => match log.get(field) {
+                Some(v) => v.get().trim_matches('"').to_string(),
+                None => {
+                    let fallback = chrono::Utc::now().timestamp_nanos().to_string();
+                    tracing::warn!(
+                        field,
+                        fallback,
+                        "row key field missing from log, falling back to a timestamp to avoid row collisions"
+                    );
+                    fallback
+                }
+            },
+            RowKeyStrategy::Composite(strategies) => strategies
+                .iter()
+                .map(|strategy| strategy.row_key(log))
+                .collect::<Vec<_>>()
+                .join("#"),
+        }
+    }
+}
+
+/// Spreads writes across HBase regions by prefixing row keys with a bucket
+/// derived from `hash(key) % num_buckets`, so monotonic/time-ordered keys
+/// don't all land on the same region server.
+#[derive(Debug, Clone, Copy)]
+pub struct Salting {
+    pub num_buckets: NonZeroU32,
+    pub width: usize,
+}
+
+impl Salting {
+    fn apply(&self, key: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let bucket = hasher.finish() % self.num_buckets.get() as u64;
+        format!("{:0width$}_{key}", bucket, width = self.width)
+    }
+}
+
+/// Row-key related settings that a [`HbaseSink`] is constructed with.
+#[derive(Debug, Clone)]
+pub struct HbaseOptions {
+    pub row_key_strategy: RowKeyStrategy,
+    pub salting: Option<Salting>,
+}
+
+/// Writes logs to HBase over Thrift via a pooled connection.
+///
+/// The table and column family are resolved per-call from the
+/// [`Destination`] passed to [`write_logs`](LogSink::write_logs), so one
+/// sink can fan writes out across many tables.
+pub struct HbaseSink {
+    pool: ConnectionPool<String>,
+    opts: HbaseOptions,
+}
+
+impl HbaseSink {
+    pub async fn connect(hbase_addr: &str, opts: HbaseOptions) -> anyhow::Result<Self> {
+        let manager = MakeThriftConnectionFromAddrs::<Client, _>::new(hbase_addr.to_string())
+            .into_connection_manager();
+        let pool = Pool::builder()
+            .connection_timeout(Duration::from_secs(5))
+            .build(manager)
+            .await?;
+        Ok(Self { pool, opts })
+    }
+}
+
+#[async_trait]
+impl LogSink for HbaseSink {
+    async fn write_logs(&self, destination: &Destination, logs: Logs) -> anyhow::Result<()> {
+        let mut conn = self.pool.get().await?;
+        let mut row_batches = Vec::new();
+        for log in logs {
+            let row_key = self.opts.row_key_strategy.row_key(&log);
+            let row_key = match &self.opts.salting {
+                Some(salting) => salting.apply(&row_key),
+                None => row_key,
+            };
+
+            let mut bmb = <BatchMutationBuilder>::default();
+            bmb.row(row_key);
+            for (k, v) in log {
+                let mut mb = MutationBuilder::default();
+                mb.value(v.get());
+                mb.column(destination.column_family.clone(), k);
+                bmb.mutation(mb);
+            }
+            row_batches.push(bmb.build());
+        }
+        conn.put(&destination.table, row_batches, None, None)?;
+        Ok(())
+    }
+
+    async fn ready(&self) -> anyhow::Result<()> {
+        self.pool.get().await?;
+        Ok(())
+    }
+
+    fn pool_state(&self) -> Option<(u32, u32)> {
+        let state = self.pool.state();
+        Some((state.connections - state.idle_connections, state.idle_connections))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(pairs: &[(&str, &str)]) -> BTreeMap<String, Box<RawValue>> {
+        pairs
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    RawValue::from_string(format!("{v:?}")).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn row_key_strategy_field_uses_the_field_value() {
+        let strategy = RowKeyStrategy::Field("id".to_string());
+        let log = log(&[("id", "abc123")]);
+        assert_eq!(strategy.row_key(&log), "abc123");
+    }
+
+    #[test]
+    fn row_key_strategy_field_falls_back_when_field_is_missing() {
+        let strategy = RowKeyStrategy::Field("id".to_string());
+        let log = log(&[("other", "abc123")]);
+        // Falls back to a non-empty, unique key instead of silently
+        // coalescing every log missing the field onto the same row.
+        assert!(!strategy.row_key(&log).is_empty());
+        assert_ne!(strategy.row_key(&log), strategy.row_key(&log_empty()));
+    }
+
+    fn log_empty() -> BTreeMap<String, Box<RawValue>> {
+        BTreeMap::new()
+    }
+
+    #[test]
+    fn row_key_strategy_composite_joins_with_hash() {
+        let strategy = RowKeyStrategy::Composite(vec![
+            RowKeyStrategy::Field("a".to_string()),
+            RowKeyStrategy::Field("b".to_string()),
+        ]);
+        let log = log(&[("a", "x"), ("b", "y")]);
+        assert_eq!(strategy.row_key(&log), "x#y");
+    }
+
+    #[test]
+    fn row_key_strategy_from_str_parses_all_forms() {
+        assert!(matches!(
+            "timestamp".parse::<RowKeyStrategy>().unwrap(),
+            RowKeyStrategy::Timestamp
+        ));
+        assert!(matches!(
+            "field:id".parse::<RowKeyStrategy>().unwrap(),
+            RowKeyStrategy::Field(field) if field == "id"
+        ));
+        assert!(matches!(
+            "composite:field:a,field:b".parse::<RowKeyStrategy>().unwrap(),
+            RowKeyStrategy::Composite(strategies) if strategies.len() == 2
+        ));
+        assert!("bogus".parse::<RowKeyStrategy>().is_err());
+    }
+
+    #[test]
+    fn salting_prefixes_the_key_with_a_zero_padded_bucket() {
+        let salting = Salting {
+            num_buckets: NonZeroU32::new(16).unwrap(),
+            width: 2,
+        };
+        let salted = salting.apply("some-row-key");
+        assert!(salted.ends_with("_some-row-key"));
+        assert_eq!(salted.len(), 2 + 1 + "some-row-key".len());
+    }
+
+    #[test]
+    fn salting_is_deterministic_for_the_same_key() {
+        let salting = Salting {
+            num_buckets: NonZeroU32::new(4).unwrap(),
+            width: 1,
+        };
+        assert_eq!(salting.apply("same-key"), salting.apply("same-key"));
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use super::{Destination, LogSink};
+use crate::Logs;
+
+/// Buffers written logs in memory, grouped by destination, instead of
+/// talking to HBase.
+///
+/// Useful for local development and integration tests that want to assert
+/// on what the handler would have written without a live HBase cluster.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    logs: Mutex<BTreeMap<Destination, Logs>>,
+}
+
+#[async_trait]
+impl LogSink for MemorySink {
+    async fn write_logs(&self, destination: &Destination, logs: Logs) -> anyhow::Result<()> {
+        self.logs
+            .lock()
+            .await
+            .entry(destination.clone())
+            .or_default()
+            .extend(logs);
+        Ok(())
+    }
+}
+
+impl MemorySink {
+    /// Snapshot of everything written so far, grouped by destination.
+    /// Only meant for tests asserting on what would have been written.
+    #[cfg(test)]
+    pub(crate) async fn snapshot(&self) -> BTreeMap<Destination, Logs> {
+        self.logs.lock().await.clone()
+    }
+}
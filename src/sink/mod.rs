@@ -0,0 +1,110 @@
+mod hbase;
+mod memory;
+mod stdout;
+
+pub use hbase::{HbaseOptions, HbaseSink, RowKeyStrategy, Salting};
+pub use memory::MemorySink;
+pub use stdout::StdoutSink;
+
+use crate::Logs;
+use async_trait::async_trait;
+
+/// Where a group of logs should be written: an HBase table and column
+/// family, resolved per-request so a single deployment can serve several
+/// tenants instead of being wired to one fixed destination.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Destination {
+    pub table: String,
+    pub column_family: String,
+}
+
+/// A destination that ingested logs are written to.
+///
+/// This keeps the axum handler backend-agnostic: it only ever talks to a
+/// `dyn LogSink`, so swapping HBase for an in-memory buffer (tests) or
+/// stdout (local debugging) is just a matter of changing the sink address.
+#[async_trait]
+pub trait LogSink: Send + Sync {
+    async fn write_logs(&self, destination: &Destination, logs: Logs) -> anyhow::Result<()>;
+
+    /// Verify the sink can actually accept writes right now (e.g. by
+    /// borrowing a pooled connection). Backs the `/readyz` endpoint.
+    /// Sinks with no meaningful readiness check are always ready.
+    async fn ready(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Pooled connection counts as `(in_use, idle)`, for sinks backed by a
+    /// connection pool. `None` if the sink has no such pool.
+    fn pool_state(&self) -> Option<(u32, u32)> {
+        None
+    }
+}
+
+/// Build a [`LogSink`] from a connection string.
+///
+/// Supported forms:
+/// - `hbase://host:port`
+/// - `memory://`
+/// - `stdout://`
+pub async fn from_addr(addr: &str, hbase_opts: HbaseOptions) -> anyhow::Result<Box<dyn LogSink>> {
+    let (scheme, rest) = addr
+        .split_once("://")
+        .ok_or_else(|| anyhow::anyhow!("sink address `{addr}` is missing a `scheme://` prefix"))?;
+
+    match scheme {
+        "hbase" => {
+            let hbase_addr = rest.trim_end_matches('/');
+            if hbase_addr.is_empty() {
+                anyhow::bail!("hbase sink address `{addr}` is missing a host");
+            }
+            Ok(Box::new(HbaseSink::connect(hbase_addr, hbase_opts).await?))
+        }
+        "memory" => Ok(Box::new(MemorySink::default())),
+        "stdout" => Ok(Box::new(StdoutSink::default())),
+        other => Err(anyhow::anyhow!("unsupported sink scheme `{other}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hbase_opts() -> HbaseOptions {
+        HbaseOptions {
+            row_key_strategy: RowKeyStrategy::Timestamp,
+            salting: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn from_addr_rejects_a_missing_scheme() {
+        let err = from_addr("localhost:9090", hbase_opts()).await.unwrap_err();
+        assert!(err.to_string().contains("missing a `scheme://` prefix"));
+    }
+
+    #[tokio::test]
+    async fn from_addr_rejects_an_unsupported_scheme() {
+        let err = from_addr("redis://localhost", hbase_opts()).await.unwrap_err();
+        assert!(err.to_string().contains("unsupported sink scheme `redis`"));
+    }
+
+    #[tokio::test]
+    async fn from_addr_rejects_an_hbase_address_with_no_host() {
+        let err = from_addr("hbase://", hbase_opts()).await.unwrap_err();
+        assert!(err.to_string().contains("missing a host"));
+    }
+
+    #[tokio::test]
+    async fn from_addr_builds_a_memory_sink() {
+        let sink = from_addr("memory://", hbase_opts()).await.unwrap();
+        assert!(sink.ready().await.is_ok());
+        assert_eq!(sink.pool_state(), None);
+    }
+
+    #[tokio::test]
+    async fn from_addr_builds_a_stdout_sink() {
+        let sink = from_addr("stdout://", hbase_opts()).await.unwrap();
+        assert!(sink.ready().await.is_ok());
+    }
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+
+use super::{Destination, LogSink};
+use crate::Logs;
+
+/// Writes each log to stdout instead of talking to HBase, one line per log:
+/// a `[table/column_family]` prefix followed by the log serialized as JSON.
+///
+/// Handy for local development when you just want to eyeball what's being
+/// ingested.
+#[derive(Debug, Default)]
+pub struct StdoutSink;
+
+#[async_trait]
+impl LogSink for StdoutSink {
+    async fn write_logs(&self, destination: &Destination, logs: Logs) -> anyhow::Result<()> {
+        for log in logs {
+            println!(
+                "[{}/{}] {}",
+                destination.table,
+                destination.column_family,
+                serde_json::to_string(&log)?
+            );
+        }
+        Ok(())
+    }
+}